@@ -0,0 +1,227 @@
+//! Base64/hex stream codecs for `--input-encoding`/`--output-encoding`.
+//!
+//! These wrap the `cin`/`cout` streams passed into `Program::step` so a
+//! brainfuck program can exchange binary data over a text-only channel
+//! without having to handle the encoding itself. Both directions are
+//! streamed in 3-byte/4-char base64 groups (or 1-byte/2-char hex groups) as
+//! data flows through, rather than buffering the whole program's output
+//! until it halts.
+
+use std::io::{self, Read, Write};
+
+use base64::{Config, STANDARD};
+
+/// Encoding applied to a program's input or output stream.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Encoding {
+    Raw,
+    Base64,
+    Hex,
+}
+
+impl Encoding {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "raw" => Some(Self::Raw),
+            "base64" => Some(Self::Base64),
+            "hex" => Some(Self::Hex),
+            _ => None,
+        }
+    }
+}
+
+const BASE64_CONFIG: Config = STANDARD;
+
+/// Decodes base64 or hex text read from `inner` into raw bytes, one encoded
+/// group at a time.
+pub struct Decoder<R> {
+    inner: R,
+    encoding: Encoding,
+    pending: Vec<u8>,
+    pending_pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(inner: R, encoding: Encoding) -> Self {
+        Decoder {
+            inner,
+            encoding,
+            pending: Vec::new(),
+            pending_pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Read one non-whitespace character, or `None` at EOF.
+    fn next_char(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.inner.read(&mut byte)? {
+                0 => return Ok(None),
+                _ if byte[0].is_ascii_whitespace() => continue,
+                _ => return Ok(Some(byte[0])),
+            }
+        }
+    }
+
+    /// Read and decode the next group (4 base64 chars or 2 hex chars) into `self.pending`.
+    fn fill(&mut self) -> io::Result<()> {
+        let group_len = match self.encoding {
+            Encoding::Base64 => 4,
+            Encoding::Hex => 2,
+            Encoding::Raw => unreachable!("Decoder is never constructed for Raw"),
+        };
+
+        let mut group = Vec::with_capacity(group_len);
+        for _ in 0..group_len {
+            match self.next_char()? {
+                Some(c) => group.push(c),
+                None => break,
+            }
+        }
+        if group.is_empty() {
+            self.eof = true;
+            return Ok(());
+        }
+        if group.len() != group_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated encoded input",
+            ));
+        }
+
+        self.pending = match self.encoding {
+            Encoding::Base64 => base64::decode_config(&group, BASE64_CONFIG)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Encoding::Hex => {
+                let s = std::str::from_utf8(&group)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                vec![u8::from_str_radix(s, 16)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?]
+            }
+            Encoding::Raw => unreachable!("Decoder is never constructed for Raw"),
+        };
+        self.pending_pos = 0;
+        if self.pending.is_empty() {
+            self.eof = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            if self.pending_pos == self.pending.len() {
+                if self.eof {
+                    break;
+                }
+                self.fill()?;
+                if self.eof {
+                    break;
+                }
+            }
+            buf[n] = self.pending[self.pending_pos];
+            self.pending_pos += 1;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+/// Encodes raw bytes written to it as base64 or hex text, written to `inner`
+/// as each 3-byte/1-byte group fills up.
+pub struct Encoder<W: Write> {
+    inner: W,
+    encoding: Encoding,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(inner: W, encoding: Encoding) -> Self {
+        Encoder {
+            inner,
+            encoding,
+            buf: Vec::with_capacity(3),
+        }
+    }
+
+    fn group_size(&self) -> usize {
+        match self.encoding {
+            Encoding::Base64 => 3,
+            Encoding::Hex => 1,
+            Encoding::Raw => unreachable!("Encoder is never constructed for Raw"),
+        }
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.encoding {
+            Encoding::Base64 => {
+                let text = base64::encode_config(bytes, BASE64_CONFIG);
+                self.inner.write_all(text.as_bytes())
+            }
+            Encoding::Hex => {
+                for b in bytes {
+                    write!(self.inner, "{b:02x}")?;
+                }
+                Ok(())
+            }
+            Encoding::Raw => unreachable!("Encoder is never constructed for Raw"),
+        }
+    }
+
+    /// Flush any buffered partial group, padding it the way the encoding
+    /// requires. Automatically called on drop, since the boxed `dyn Write`
+    /// streams in `main` can't be consumed to call a `self`-taking finish.
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            let buf = std::mem::take(&mut self.buf);
+            self.emit(&buf)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let group_size = self.group_size();
+        for &b in data {
+            self.buf.push(b);
+            if self.buf.len() == group_size {
+                let group = std::mem::take(&mut self.buf);
+                self.emit(&group)?;
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Encoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Build the stream passed to `Program::step` for `,`, decoding `encoding`
+/// from stdin unless it's `Raw`.
+pub fn input_stream(encoding: Encoding) -> Box<dyn Read> {
+    match encoding {
+        Encoding::Raw => Box::new(io::stdin()),
+        encoding => Box::new(Decoder::new(io::stdin(), encoding)),
+    }
+}
+
+/// Build the stream passed to `Program::step` for `.`, encoding bytes to
+/// `encoding` before they reach stdout unless it's `Raw`.
+pub fn output_stream(encoding: Encoding) -> Box<dyn Write> {
+    match encoding {
+        Encoding::Raw => Box::new(io::stdout()),
+        encoding => Box::new(Encoder::new(io::stdout(), encoding)),
+    }
+}
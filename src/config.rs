@@ -0,0 +1,82 @@
+//! Tape and cell semantics that `step` otherwise hard-codes, exposed as a
+//! config struct so callers can target other brainfuck dialects.
+
+/// Cell width, widened as needed but always masked back down after every
+/// arithmetic op so cells still wrap at the configured width.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum CellWidth {
+    Eight,
+    Sixteen,
+    ThirtyTwo,
+}
+
+impl CellWidth {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "8" => Some(Self::Eight),
+            "16" => Some(Self::Sixteen),
+            "32" => Some(Self::ThirtyTwo),
+            _ => None,
+        }
+    }
+
+    /// The all-ones value for this width, and the mask applied after every
+    /// write to a cell.
+    pub fn mask(self) -> u32 {
+        match self {
+            Self::Eight => 0xff,
+            Self::Sixteen => 0xffff,
+            Self::ThirtyTwo => 0xffff_ffff,
+        }
+    }
+}
+
+/// What `,` does to the current cell when stdin is at EOF.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum EofBehavior {
+    /// Leave the cell unchanged (the interpreter's long-standing default).
+    Unchanged,
+    /// Set the cell to zero.
+    Zero,
+    /// Set the cell to all-ones, i.e. -1 at the configured cell width.
+    NegOne,
+}
+
+impl EofBehavior {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "unchanged" => Some(Self::Unchanged),
+            "zero" => Some(Self::Zero),
+            "neg-one" => Some(Self::NegOne),
+            _ => None,
+        }
+    }
+}
+
+/// Tape and cell policy used by `Program`.
+#[derive(Copy, Clone)]
+pub struct Config {
+    pub eof: EofBehavior,
+    pub cell_width: CellWidth,
+    /// `Some(n)` for a fixed-size tape of `n` cells; `None` for the
+    /// interpreter's default tape that grows in 1024-cell chunks.
+    pub tape_size: Option<usize>,
+    /// Wrap the pointer modulo `tape_size` instead of halting at the edges.
+    /// Only meaningful when `tape_size` is `Some`.
+    pub wrap_tape: bool,
+    /// Treat moving the pointer out of range as an error instead of a
+    /// silent halt.
+    pub strict_bounds: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            eof: EofBehavior::Unchanged,
+            cell_width: CellWidth::Eight,
+            tape_size: None,
+            wrap_tape: false,
+            strict_bounds: false,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Interactive stepping debugger for `--debug` mode.
+//!
+//! Wraps `Program::step` in a REPL with breakpoints and a tape inspector,
+//! using `rustyline` for line editing and persistent up-arrow history in a
+//! dotfile, the same way an interactive shell front-end would.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+
+use crate::config::CellWidth;
+use crate::Program;
+
+const HISTORY_FILE: &str = ".rainfuck_history";
+const TAPE_RADIUS: usize = 8;
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// Run `prog` interactively until it halts or the user quits, reading `,`
+/// from `cin` and writing `.` to `cout`.
+pub fn run<R: io::Read, W: Write>(mut prog: Program, cin: &mut R, cout: &mut W) -> Result<()> {
+    let mut rl = Editor::<()>::new();
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = rl.load_history(path);
+    }
+
+    let mut breakpoints = HashSet::new();
+    let mut halted = false;
+
+    println!("rainfuck debugger. Type 'h' for help.");
+    loop {
+        let line = match rl.readline(if halted { "(halted) > " } else { "(rainfuck) > " }) {
+            Ok(line) => line,
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => break,
+            Err(e) => return Err(e).context("readline error"),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        rl.add_history_entry(line);
+
+        let mut parts = line.split_whitespace();
+        match parts.next().unwrap_or("") {
+            "s" | "step" => {
+                if halted {
+                    println!("program has halted");
+                } else {
+                    halted = !step_n(&mut prog, 1, cin, cout)?;
+                }
+            }
+            "n" => {
+                let n: usize = parts.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+                if halted {
+                    println!("program has halted");
+                } else {
+                    halted = !step_n(&mut prog, n, cin, cout)?;
+                }
+            }
+            "c" | "continue" => {
+                if halted {
+                    println!("program has halted");
+                } else {
+                    halted = !run_to_breakpoint(&mut prog, &breakpoints, cin, cout)?;
+                }
+            }
+            "b" => match parts.next().and_then(|s| s.parse().ok()) {
+                Some(pc) => {
+                    if breakpoints.remove(&pc) {
+                        println!("breakpoint at {} cleared", pc);
+                    } else {
+                        breakpoints.insert(pc);
+                        println!("breakpoint set at {}", pc);
+                    }
+                }
+                None => println!("usage: b PC"),
+            },
+            "p" | "print" => print_state(&prog),
+            "h" | "help" => print_help(),
+            "q" | "quit" => break,
+            cmd => println!("unknown command {:?}, type 'h' for help", cmd),
+        }
+    }
+
+    if let Some(path) = &history {
+        let _ = rl.save_history(path);
+    }
+    Ok(())
+}
+
+/// Step `prog` forward `n` times, stopping early if it halts.
+/// Returns `Ok(true)` if the program is still running afterward.
+fn step_n<R: io::Read, W: Write>(
+    prog: &mut Program,
+    n: usize,
+    cin: &mut R,
+    cout: &mut W,
+) -> Result<bool> {
+    for _ in 0..n {
+        if !prog.step(cin, cout).context("IO error")? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Run `prog` until it halts or its pc lands on a breakpoint.
+/// Returns `Ok(true)` if the program is still running afterward.
+fn run_to_breakpoint<R: io::Read, W: Write>(
+    prog: &mut Program,
+    breakpoints: &HashSet<usize>,
+    cin: &mut R,
+    cout: &mut W,
+) -> Result<bool> {
+    loop {
+        if !prog.step(cin, cout).context("IO error")? {
+            return Ok(false);
+        }
+        if breakpoints.contains(&prog.pc()) {
+            println!("hit breakpoint at {}", prog.pc());
+            return Ok(true);
+        }
+    }
+}
+
+fn print_state(prog: &Program) {
+    match prog.op_at(prog.pc()) {
+        Some(op) => println!("pc={} ptr={} op={:?}", prog.pc(), prog.ptr(), op),
+        None => println!("pc={} ptr={} op=<halted>", prog.pc(), prog.ptr()),
+    }
+    print_tape(prog);
+}
+
+/// Print a hex+ASCII view of the tape within `TAPE_RADIUS` cells of `ptr`,
+/// with the current cell bracketed.
+fn print_tape(prog: &Program) {
+    let digits = match prog.cell_width() {
+        CellWidth::Eight => 2,
+        CellWidth::Sixteen => 4,
+        CellWidth::ThirtyTwo => 8,
+    };
+    let ptr = prog.ptr();
+    let data = prog.tape();
+    let start = ptr.saturating_sub(TAPE_RADIUS);
+    let end = (ptr + TAPE_RADIUS + 1).min(data.len());
+    let window = &data[start..end];
+
+    print!("{:>6}: ", start);
+    for (i, b) in window.iter().enumerate() {
+        if start + i == ptr {
+            print!("[{b:0digits$x}] ");
+        } else {
+            print!("{b:0digits$x} ");
+        }
+    }
+    println!();
+
+    print!("{:>6}  ", "");
+    for b in window {
+        let c = if *b <= 0xff && (b' '..=b'~').contains(&(*b as u8)) {
+            *b as u8 as char
+        } else {
+            '.'
+        };
+        print!("{c:>digits$} ");
+    }
+    println!();
+}
+
+fn print_help() {
+    println!("commands:");
+    println!("  s, step        execute a single command");
+    println!("  n N            execute N commands");
+    println!("  c, continue    run until a breakpoint or halt");
+    println!("  b PC           toggle a breakpoint on command index PC");
+    println!("  p, print       print pc, ptr, current op, and a tape window");
+    println!("  h, help        print this message");
+    println!("  q, quit        exit the debugger");
+}
@@ -0,0 +1,179 @@
+//! Transpiler backends: compile an optimized `Op` stream to standalone C or
+//! Rust source, for users who want a native binary instead of interpreting.
+//! Since we emit from the IR, `Clear` and `MulAdd` become single statements
+//! instead of a loop.
+
+use std::fmt::Write as _;
+
+use crate::ir::Op;
+
+/// Target language for `emit`.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Lang {
+    C,
+    Rust,
+}
+
+impl Lang {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "c" => Some(Self::C),
+            "rust" => Some(Self::Rust),
+            _ => None,
+        }
+    }
+}
+
+/// Emit standalone source code implementing `ops` in `lang`.
+pub fn emit(ops: &[Op], lang: Lang) -> String {
+    let mut out = String::new();
+    match lang {
+        Lang::C => {
+            out.push_str(C_PROLOGUE);
+            emit_block(&mut out, ops, 0, ops.len(), 1, lang);
+            out.push_str(C_EPILOGUE);
+        }
+        Lang::Rust => {
+            out.push_str(RUST_PROLOGUE);
+            emit_block(&mut out, ops, 0, ops.len(), 1, lang);
+            out.push_str(RUST_EPILOGUE);
+        }
+    }
+    out
+}
+
+const C_PROLOGUE: &str = "\
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static uint8_t *tape;
+static size_t len, p;
+
+static void grow(size_t needed) {
+    size_t newlen = ((needed / 1024) + 1) * 1024;
+    tape = realloc(tape, newlen);
+    memset(tape + len, 0, newlen - len);
+    len = newlen;
+}
+
+int main(void) {
+    grow(1024);
+";
+const C_EPILOGUE: &str = "    return 0;\n}\n";
+
+const RUST_PROLOGUE: &str = "\
+use std::io::{self, Read, Write};
+
+fn main() {
+    let mut tape: Vec<u8> = vec![0u8; 1024];
+    let mut p: usize = 0;
+";
+const RUST_EPILOGUE: &str = "}\n";
+
+/// Emit `ops[lo..hi]`, recursing into `while` bodies using their baked jump
+/// targets to find the matching loop close.
+fn emit_block(out: &mut String, ops: &[Op], lo: usize, hi: usize, indent: usize, lang: Lang) {
+    let pad = "    ".repeat(indent);
+    let mut i = lo;
+    while i < hi {
+        match ops[i] {
+            Op::JumpIfZero(target) => {
+                match lang {
+                    Lang::C => writeln!(out, "{pad}while (tape[p] != 0) {{").unwrap(),
+                    Lang::Rust => writeln!(out, "{pad}while tape[p] != 0 {{").unwrap(),
+                }
+                emit_block(out, ops, i + 1, target, indent + 1, lang);
+                writeln!(out, "{pad}}}").unwrap();
+                i = target + 1;
+            }
+            Op::JumpIfNonZero(_) => unreachable!("closed by the matching JumpIfZero"),
+            op => {
+                emit_op(out, op, &pad, lang);
+                i += 1;
+            }
+        }
+    }
+}
+
+fn emit_op(out: &mut String, op: Op, pad: &str, lang: Lang) {
+    match (op, lang) {
+        (Op::Move(n), Lang::C) if n >= 0 => {
+            writeln!(out, "{pad}p += {n}; if (p >= len) grow(p + 1);").unwrap();
+        }
+        (Op::Move(n), Lang::C) => {
+            let n = -n;
+            writeln!(out, "{pad}if (p < {n}) return 0; p -= {n};").unwrap();
+        }
+        (Op::Move(n), Lang::Rust) if n >= 0 => {
+            writeln!(
+                out,
+                "{pad}p += {n}; if p >= tape.len() {{ tape.resize(((p / 1024) + 1) * 1024, 0); }}"
+            )
+            .unwrap();
+        }
+        (Op::Move(n), Lang::Rust) => {
+            let n = -n;
+            writeln!(out, "{pad}if p < {n} {{ return; }} p -= {n};").unwrap();
+        }
+        (Op::Add(n), Lang::C) => {
+            writeln!(out, "{pad}tape[p] = (uint8_t)(tape[p] + ({n}));").unwrap();
+        }
+        (Op::Add(n), Lang::Rust) => {
+            writeln!(out, "{pad}tape[p] = tape[p].wrapping_add(({n}i32) as u8);").unwrap();
+        }
+        (Op::Out, Lang::C) => {
+            writeln!(out, "{pad}putchar(tape[p]);").unwrap();
+        }
+        (Op::Out, Lang::Rust) => {
+            writeln!(out, "{pad}io::stdout().write_all(&[tape[p]]).unwrap();").unwrap();
+        }
+        (Op::In, Lang::C) => {
+            writeln!(out, "{pad}{{ int c = getchar(); if (c != EOF) tape[p] = (uint8_t)c; }}")
+                .unwrap();
+        }
+        (Op::In, Lang::Rust) => {
+            writeln!(out, "{pad}{{").unwrap();
+            writeln!(out, "{pad}    let mut b = [0u8; 1];").unwrap();
+            writeln!(out, "{pad}    if io::stdin().read_exact(&mut b).is_ok() {{ tape[p] = b[0]; }}").unwrap();
+            writeln!(out, "{pad}}}").unwrap();
+        }
+        (Op::Clear, Lang::C) | (Op::Clear, Lang::Rust) => {
+            writeln!(out, "{pad}tape[p] = 0;").unwrap();
+        }
+        (Op::MulAdd { offset, factor }, Lang::C) => {
+            writeln!(out, "{pad}{{").unwrap();
+            writeln!(out, "{pad}    ptrdiff_t t = (ptrdiff_t)p + ({offset});").unwrap();
+            writeln!(out, "{pad}    if (t < 0) return 0;").unwrap();
+            writeln!(out, "{pad}    if ((size_t)t >= len) grow((size_t)t + 1);").unwrap();
+            writeln!(
+                out,
+                "{pad}    tape[(size_t)t] = (uint8_t)(tape[(size_t)t] + (uint8_t)(tape[p] * ({factor})));"
+            )
+            .unwrap();
+            writeln!(out, "{pad}}}").unwrap();
+        }
+        (Op::MulAdd { offset, factor }, Lang::Rust) => {
+            writeln!(out, "{pad}{{").unwrap();
+            writeln!(out, "{pad}    let t = p as isize + ({offset});").unwrap();
+            writeln!(out, "{pad}    if t < 0 {{ return; }}").unwrap();
+            writeln!(out, "{pad}    let t = t as usize;").unwrap();
+            writeln!(
+                out,
+                "{pad}    if t >= tape.len() {{ tape.resize(((t / 1024) + 1) * 1024, 0); }}"
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{pad}    tape[t] = tape[t].wrapping_add(tape[p].wrapping_mul(({factor}i32) as u8));"
+            )
+            .unwrap();
+            writeln!(out, "{pad}}}").unwrap();
+        }
+        (Op::JumpIfZero(_), _) | (Op::JumpIfNonZero(_), _) => {
+            unreachable!("handled by emit_block")
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Optimizing instruction IR.
+//!
+//! The `Cmd` stream that `Program::parse` filters out of the source is a flat
+//! one-opcode-per-character stream; interpreted directly, every `[`/`]` would
+//! pay for a `HashMap` lookup and every `+`/`>` only changes the tape by one. `compile` lowers that stream into a `Vec<Op>` instead: runs of
+//! `+`/`-` and `>`/`<` are coalesced into single ops, common loop idioms are
+//! folded into `Clear`/`MulAdd`, and bracket targets are resolved to absolute
+//! indices so `Program::step` never needs to consult a jump table.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::Cmd;
+
+/// A single compiled instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Add `n` to the current cell, wrapping at the configured cell width.
+    Add(i32),
+    /// Move the pointer by `n` cells.
+    Move(isize),
+    Out,
+    In,
+    /// Jump to `target` if the current cell is zero.
+    JumpIfZero(usize),
+    /// Jump to `target` if the current cell is nonzero.
+    JumpIfNonZero(usize),
+    /// Set the current cell to zero. Folded from `[-]`/`[+]`.
+    Clear,
+    /// `data[ptr + offset] += data[ptr] * factor`, wrapping at the
+    /// configured cell width. Folded from simple copy/multiply loops; always
+    /// followed by a `Clear` that zeroes the source cell the loop would
+    /// otherwise have zeroed.
+    MulAdd { offset: isize, factor: i32 },
+}
+
+/// Compile a flat `Cmd` stream into an optimized `Op` stream.
+pub fn compile(cmds: &[Cmd]) -> Result<Vec<Op>> {
+    let mut ops = coalesce(cmds);
+    let matches = match_brackets(&ops)?;
+    fold_idioms(&mut ops, &matches);
+    resolve_jumps(&mut ops)?;
+    Ok(ops)
+}
+
+/// Coalesce runs of `+`/`-` into a single `Add` and runs of `>`/`<` into a
+/// single `Move`. Jump targets are left as placeholders; they're filled in by
+/// `resolve_jumps` once idiom folding has settled on a final op list.
+fn coalesce(cmds: &[Cmd]) -> Vec<Op> {
+    let mut ops = Vec::with_capacity(cmds.len());
+    for &cmd in cmds {
+        let delta = match cmd {
+            Cmd::Inc => Some(1i32),
+            Cmd::Dec => Some(-1i32),
+            _ => None,
+        };
+        if let Some(delta) = delta {
+            if let Some(Op::Add(n)) = ops.last_mut() {
+                *n += delta;
+                continue;
+            }
+            ops.push(Op::Add(delta));
+            continue;
+        }
+
+        let step = match cmd {
+            Cmd::Right => Some(1isize),
+            Cmd::Left => Some(-1isize),
+            _ => None,
+        };
+        if let Some(step) = step {
+            if let Some(Op::Move(n)) = ops.last_mut() {
+                *n += step;
+                continue;
+            }
+            ops.push(Op::Move(step));
+            continue;
+        }
+
+        ops.push(match cmd {
+            Cmd::Out => Op::Out,
+            Cmd::In => Op::In,
+            Cmd::Start => Op::JumpIfZero(0),
+            Cmd::End => Op::JumpIfNonZero(0),
+            Cmd::Inc | Cmd::Dec | Cmd::Right | Cmd::Left => unreachable!("handled above"),
+        });
+    }
+    ops
+}
+
+/// Match every `JumpIfZero`/`JumpIfNonZero` placeholder pair by index.
+fn match_brackets(ops: &[Op]) -> Result<HashMap<usize, usize>> {
+    let mut matches = HashMap::new();
+    let mut stack = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            Op::JumpIfZero(_) => stack.push(i),
+            Op::JumpIfNonZero(_) => match stack.pop() {
+                Some(start) => {
+                    matches.insert(start, i);
+                    matches.insert(i, start);
+                }
+                None => return Err(anyhow!("unmatched ]")),
+            },
+            _ => (),
+        }
+    }
+    if !stack.is_empty() {
+        return Err(anyhow!("unmatched ["));
+    }
+    Ok(matches)
+}
+
+/// Recognize `[-]`/`[+]` as `Clear` and simple copy/multiply loops as
+/// `MulAdd` + `Clear`, rebuilding `ops` in place. Loops that don't match a
+/// known idiom are left untouched and fall back to plain jumps.
+fn fold_idioms(ops: &mut Vec<Op>, matches: &HashMap<usize, usize>) {
+    let old = std::mem::take(ops);
+    let mut i = 0;
+    while i < old.len() {
+        if let Op::JumpIfZero(_) = old[i] {
+            let end = matches[&i];
+            let body = &old[i + 1..end];
+            if let Some(folded) = fold_loop_body(body) {
+                ops.extend(folded);
+                i = end + 1;
+                continue;
+            }
+        }
+        ops.push(old[i]);
+        i += 1;
+    }
+}
+
+/// Try to fold a single loop body (the ops strictly between `[` and `]`)
+/// into `Clear` or `MulAdd`+`Clear`. Returns `None` if the body contains
+/// anything other than `Add`/`Move`, doesn't return the pointer to its
+/// starting position, or doesn't decrement the current cell by exactly one
+/// per iteration.
+fn fold_loop_body(body: &[Op]) -> Option<Vec<Op>> {
+    if body == [Op::Add(1)] || body == [Op::Add(-1)] {
+        return Some(vec![Op::Clear]);
+    }
+
+    let mut rel: isize = 0;
+    let mut deltas: HashMap<isize, i32> = HashMap::new();
+    for op in body {
+        match *op {
+            Op::Move(n) => rel += n,
+            Op::Add(n) => {
+                let entry = deltas.entry(rel).or_insert(0);
+                *entry += n;
+            }
+            _ => return None,
+        }
+    }
+    if rel != 0 || deltas.get(&0) != Some(&-1) {
+        return None;
+    }
+
+    let mut offsets: Vec<_> = deltas
+        .into_iter()
+        .filter(|&(offset, factor)| offset != 0 && factor != 0)
+        .collect();
+    offsets.sort_by_key(|&(offset, _)| offset);
+
+    let mut folded: Vec<Op> = offsets
+        .into_iter()
+        .map(|(offset, factor)| Op::MulAdd { offset, factor })
+        .collect();
+    folded.push(Op::Clear);
+    Some(folded)
+}
+
+/// Bake final jump targets into every `JumpIfZero`/`JumpIfNonZero` now that
+/// the op list is final.
+fn resolve_jumps(ops: &mut [Op]) -> Result<()> {
+    let mut stack = Vec::new();
+    for i in 0..ops.len() {
+        match ops[i] {
+            Op::JumpIfZero(_) => stack.push(i),
+            Op::JumpIfNonZero(_) => {
+                let start = stack.pop().ok_or_else(|| anyhow!("unmatched ]"))?;
+                ops[start] = Op::JumpIfZero(i);
+                ops[i] = Op::JumpIfNonZero(start);
+            }
+            _ => (),
+        }
+    }
+    if !stack.is_empty() {
+        return Err(anyhow!("unmatched ["));
+    }
+    Ok(())
+}
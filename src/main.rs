@@ -1,12 +1,22 @@
 //! rainfuck: A simple Rust brainfuck interpreter.
 
-use std::collections::HashMap;
+mod codec;
+mod config;
+mod debug;
+mod emit;
+mod ir;
+
 use std::fs::File;
 use std::io::{self, BufReader, Read, Write};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use clap::{App, Arg, ArgGroup};
 
+use codec::Encoding;
+use config::{CellWidth, Config, EofBehavior};
+use emit::Lang;
+use ir::Op;
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum Cmd {
     Right,
@@ -36,58 +46,81 @@ impl Cmd {
 }
 
 struct Program {
-    cmds: Vec<Cmd>,
-    data: Vec<u8>,
+    ops: Vec<Op>,
+    data: Vec<u32>,
     pc: usize,
     ptr: usize,
-    loops: HashMap<usize, usize>,
+    config: Config,
 }
 
 impl Program {
-    pub fn parse(code: &[u8]) -> Result<Self> {
-        let mut prog = Program {
-            cmds: Vec::new(),
-            data: vec![0; 1024],
+    pub fn parse(code: &[u8], config: Config) -> Result<Self> {
+        let cmds: Vec<Cmd> = code.iter().filter_map(|b| Cmd::from_byte(*b)).collect();
+        let ops = ir::compile(&cmds)?;
+        let data = vec![0; config.tape_size.unwrap_or(1024)];
+        Ok(Program {
+            ops,
+            data,
             pc: 0,
             ptr: 0,
-            loops: HashMap::new(),
-        };
-
-        let mut startstack = Vec::new();
-        for (i, cmd) in code.iter().filter_map(|b| Cmd::from_byte(*b)).enumerate() {
-            prog.cmds.push(cmd);
-            if cmd == Cmd::Start {
-                startstack.push(i);
-            } else if cmd == Cmd::End {
-                match startstack.pop() {
-                    Some(start) => {
-                        prog.loops.insert(start, i);
-                        prog.loops.insert(i, start);
-                    }
-                    None => return Err(anyhow!("unmatched ]")),
-                };
-            }
-        }
-        if !startstack.is_empty() {
-            return Err(anyhow!("unmatched ["));
-        }
-        Ok(prog)
+            config,
+        })
     }
 
-    pub fn load<R: Read>(mut file: R) -> Result<Self> {
+    pub fn load<R: Read>(mut file: R, config: Config) -> Result<Self> {
         let mut data = Vec::new();
         file.read_to_end(&mut data)?;
-        Self::parse(&data)
+        Self::parse(&data, config)
     }
 
     #[inline(always)]
-    fn cell(&self) -> u8 {
+    fn cell(&self) -> u32 {
         self.data[self.ptr]
     }
 
     #[inline(always)]
-    fn set_cell(&mut self, val: u8) {
-        self.data[self.ptr] = val;
+    fn set_cell(&mut self, val: u32) {
+        self.data[self.ptr] = val & self.config.cell_width.mask();
+    }
+
+    /// Resolve `self.ptr as isize + offset` to an absolute tape index under
+    /// the configured tape-size/wrap/strict-bounds policy, growing the tape
+    /// if it's unbounded and the address runs past the end.
+    /// `Ok(Some(idx))` is a valid address; `Ok(None)` means the program
+    /// should halt (a non-strict out-of-bounds move); `Err` is a
+    /// strict-bounds violation.
+    fn resolve_addr(&mut self, offset: isize) -> Result<Option<usize>, io::Error> {
+        let target = self.ptr as isize + offset;
+        let out_of_bounds = || io::Error::new(io::ErrorKind::InvalidInput, "tape pointer out of bounds");
+        match self.config.tape_size {
+            Some(size) if self.config.wrap_tape => {
+                Ok(Some(target.rem_euclid(size as isize) as usize))
+            }
+            Some(size) => {
+                if target < 0 || target as usize >= size {
+                    return if self.config.strict_bounds {
+                        Err(out_of_bounds())
+                    } else {
+                        Ok(None)
+                    };
+                }
+                Ok(Some(target as usize))
+            }
+            None => {
+                if target < 0 {
+                    return if self.config.strict_bounds {
+                        Err(out_of_bounds())
+                    } else {
+                        Ok(None)
+                    };
+                }
+                let target = target as usize;
+                if target >= self.data.len() {
+                    self.data.resize((target / 1024 + 1) * 1024, 0);
+                }
+                Ok(Some(target))
+            }
+        }
     }
 
     /// Execute the instruction at self.pc.
@@ -99,66 +132,100 @@ impl Program {
         cin: &mut R,
         cout: &mut W,
     ) -> Result<bool, io::Error> {
-        if self.pc >= self.cmds.len() {
+        if self.pc >= self.ops.len() {
             return Ok(false);
         }
-        match self.cmds[self.pc] {
-            Cmd::Right => {
-                if self.ptr == self.data.len() {
-                    self.data.resize(self.data.len() + 1024, 0);
-                }
-                self.ptr += 1;
-            }
-            Cmd::Left => {
-                if self.ptr == 0 {
-                    return Ok(false);
-                }
-                self.ptr -= 1;
-            }
-            Cmd::Inc => {
-                self.set_cell(self.cell().wrapping_add(1));
+        match self.ops[self.pc] {
+            Op::Move(n) => match self.resolve_addr(n)? {
+                Some(idx) => self.ptr = idx,
+                None => return Ok(false),
+            },
+            Op::Add(n) => {
+                self.set_cell(self.cell().wrapping_add(n as u32));
             }
-            Cmd::Dec => {
-                self.set_cell(self.cell().wrapping_sub(1));
+            Op::Out => {
+                cout.write_all(&[self.cell() as u8])?;
             }
-            Cmd::Out => {
-                cout.write_all(&[self.cell()])?;
-            }
-            Cmd::In => {
+            Op::In => {
                 let mut b = [0u8];
                 match cin.read_exact(&mut b) {
-                    Ok(_) => self.set_cell(b[0]),
-                    Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+                    Ok(_) => self.set_cell(b[0] as u32),
+                    Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => match self.config.eof
+                    {
+                        EofBehavior::Unchanged => (),
+                        EofBehavior::Zero => self.set_cell(0),
+                        EofBehavior::NegOne => self.set_cell(self.config.cell_width.mask()),
+                    },
                     Err(err) => return Err(err),
                 }
             }
-            Cmd::Start => {
-                if self.data[self.ptr] == 0 {
-                    self.pc = self.loops[&self.pc];
+            Op::JumpIfZero(target) => {
+                if self.cell() == 0 {
+                    self.pc = target;
                 }
             }
-            Cmd::End => {
-                if self.data[self.ptr] != 0 {
-                    self.pc = self.loops[&self.pc];
+            Op::JumpIfNonZero(target) => {
+                if self.cell() != 0 {
+                    self.pc = target;
                 }
             }
+            Op::Clear => {
+                self.set_cell(0);
+            }
+            Op::MulAdd { offset, factor } => match self.resolve_addr(offset)? {
+                Some(idx) => {
+                    let add = self.cell().wrapping_mul(factor as u32);
+                    self.data[idx] = (self.data[idx].wrapping_add(add)) & self.config.cell_width.mask();
+                }
+                None => return Ok(false),
+            },
         }
         self.pc += 1;
         Ok(true)
     }
 
-    /// Run the program to completion, exiting early with Err if an IO error is encountered
-    pub fn run_stdio(&mut self) -> Result<(), io::Error> {
-        let mut cin = io::stdin();
-        let mut cout = io::stdout();
+    /// The index of the next command to execute.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The current tape pointer.
+    pub fn ptr(&self) -> usize {
+        self.ptr
+    }
+
+    /// The decoded op at `pc`, or `None` if `pc` is past the end of the program.
+    pub fn op_at(&self, pc: usize) -> Option<Op> {
+        self.ops.get(pc).copied()
+    }
+
+    /// The full tape, for inspection by the debugger.
+    pub fn tape(&self) -> &[u32] {
+        &self.data
+    }
+
+    /// The configured cell width, for formatting the debugger's tape dump.
+    pub fn cell_width(&self) -> CellWidth {
+        self.config.cell_width
+    }
+
+    /// The compiled op stream, for the transpiler backend.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Run the program to completion against `cin`/`cout`, exiting early
+    /// with Err if an IO error is encountered.
+    pub fn run<R: Read, W: Write>(&mut self, cin: &mut R, cout: &mut W) -> Result<(), io::Error> {
         loop {
-            match self.step(&mut cin, &mut cout) {
+            match self.step(cin, cout) {
                 Ok(true) => (),
                 Ok(false) => break Ok(()),
                 Err(e) => break Err(e),
             }
         }
     }
+
 }
 
 fn run() -> Result<()> {
@@ -178,6 +245,85 @@ fn run() -> Result<()> {
                 .value_name("FILE")
                 .help("Source file to run. Required unless -e is used."),
         )
+        .arg(
+            Arg::with_name("debug")
+                .short("d")
+                .long("debug")
+                .help("Drop into an interactive stepping debugger instead of running straight through"),
+        )
+        .arg(
+            Arg::with_name("emit")
+                .long("emit")
+                .takes_value(true)
+                .value_name("LANG")
+                .possible_values(&["c", "rust"])
+                .conflicts_with("debug")
+                .help("Compile the program to standalone C or Rust source instead of running it"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("emit")
+                .help("Write emitted source to FILE instead of stdout"),
+        )
+        .arg(
+            Arg::with_name("eof")
+                .long("eof")
+                .takes_value(true)
+                .value_name("BEHAVIOR")
+                .possible_values(&["unchanged", "zero", "neg-one"])
+                .default_value("unchanged")
+                .help("What ',' does to the current cell when stdin is at EOF"),
+        )
+        .arg(
+            Arg::with_name("cell-size")
+                .long("cell-size")
+                .takes_value(true)
+                .value_name("BITS")
+                .possible_values(&["8", "16", "32"])
+                .default_value("8")
+                .help("Cell width in bits"),
+        )
+        .arg(
+            Arg::with_name("tape-size")
+                .long("tape-size")
+                .takes_value(true)
+                .value_name("N")
+                .help("Use a fixed-size tape of N cells instead of growing it forever"),
+        )
+        .arg(
+            Arg::with_name("wrap-tape")
+                .long("wrap-tape")
+                .requires("tape-size")
+                .help("Wrap the pointer modulo --tape-size instead of halting at the edges"),
+        )
+        .arg(
+            Arg::with_name("strict-bounds")
+                .long("strict-bounds")
+                .conflicts_with("wrap-tape")
+                .help("Treat moving the pointer out of range as an error instead of a silent halt"),
+        )
+        .arg(
+            Arg::with_name("input-encoding")
+                .long("input-encoding")
+                .takes_value(true)
+                .value_name("ENCODING")
+                .possible_values(&["raw", "base64", "hex"])
+                .default_value("raw")
+                .help("Decode stdin as this encoding before feeding it to ','"),
+        )
+        .arg(
+            Arg::with_name("output-encoding")
+                .long("output-encoding")
+                .takes_value(true)
+                .value_name("ENCODING")
+                .possible_values(&["raw", "base64", "hex"])
+                .default_value("raw")
+                .help("Encode the bytes written by '.' as this encoding before writing to stdout"),
+        )
         .group(
             ArgGroup::with_name("input")
                 .arg("code")
@@ -186,17 +332,50 @@ fn run() -> Result<()> {
         )
         .get_matches();
 
-    let mut prog = if args.is_present("code") {
-        Program::parse(args.value_of("code").unwrap().as_bytes())
+    let config = Config {
+        eof: EofBehavior::from_str(args.value_of("eof").unwrap()).unwrap(),
+        cell_width: CellWidth::from_str(args.value_of("cell-size").unwrap()).unwrap(),
+        tape_size: args
+            .value_of("tape-size")
+            .map(|n| n.parse().context("invalid --tape-size"))
+            .transpose()?,
+        wrap_tape: args.is_present("wrap-tape"),
+        strict_bounds: args.is_present("strict-bounds"),
+    };
+
+    let prog = if args.is_present("code") {
+        Program::parse(args.value_of("code").unwrap().as_bytes(), config)
     } else {
-        Program::load(BufReader::new(
-            File::open(args.value_of("file").unwrap()).context("error opening input file")?,
-        ))
+        Program::load(
+            BufReader::new(
+                File::open(args.value_of("file").unwrap()).context("error opening input file")?,
+            ),
+            config,
+        )
     }
     .context("failed to parse program")?;
 
-    prog.run_stdio().context("IO Error")?;
-    Ok(())
+    if let Some(lang) = args.value_of("emit") {
+        let lang = Lang::from_str(lang).expect("validated by possible_values");
+        let source = emit::emit(prog.ops(), lang);
+        match args.value_of("output") {
+            Some(path) => std::fs::write(path, source).context("error writing output file")?,
+            None => io::stdout().write_all(source.as_bytes())?,
+        }
+        return Ok(());
+    }
+
+    let input_encoding = Encoding::from_str(args.value_of("input-encoding").unwrap()).unwrap();
+    let output_encoding = Encoding::from_str(args.value_of("output-encoding").unwrap()).unwrap();
+    let mut cin = codec::input_stream(input_encoding);
+    let mut cout = codec::output_stream(output_encoding);
+
+    if args.is_present("debug") {
+        debug::run(prog, &mut cin, &mut cout)
+    } else {
+        let mut prog = prog;
+        prog.run(&mut cin, &mut cout).context("IO Error")
+    }
 }
 
 fn main() {